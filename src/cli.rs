@@ -1,4 +1,4 @@
-use clap::{Args, Parser};
+use clap::{Args, Parser, ValueEnum};
 use codex_common::CliConfigOverrides;
 use std::path::PathBuf;
 
@@ -10,11 +10,45 @@ pub struct CliArgs {
     /// Session persistence flags
     #[command(flatten)]
     pub session: SessionPersistCli,
+    /// Vector store backend for the code-search index
+    #[command(flatten)]
+    pub memory: MemoryCli,
     /// Use Codex's native shell sandbox instead of ACP terminal proxy.
     #[arg(long = "native-shell")]
     pub native_shell: bool,
 }
 
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+#[value(rename_all = "lowercase")]
+pub enum MemoryBackend {
+    /// In-process vector index, serialized to the session-persist directory
+    /// when one is set (otherwise rebuilt per run).
+    #[default]
+    Local,
+    /// External Postgres+pgvector service shared across sessions.
+    Postgres,
+}
+
+#[derive(Args, Debug, Default, Clone)]
+pub struct MemoryCli {
+    /// Vector store backend for the code-search index.
+    #[arg(long = "memory-backend", value_enum, default_value_t = MemoryBackend::Local)]
+    memory_backend: MemoryBackend,
+    /// Connection string for the `postgres` backend (e.g. `postgres://…`).
+    #[arg(long = "memory-url", value_name = "url")]
+    memory_url: Option<String>,
+}
+
+impl MemoryCli {
+    pub fn backend(&self) -> MemoryBackend {
+        self.memory_backend
+    }
+
+    pub fn url(&self) -> Option<&str> {
+        self.memory_url.as_deref()
+    }
+}
+
 #[derive(Args, Debug, Default, Clone)]
 pub struct SessionPersistCli {
     /// Enable session persistence. Optionally provide a directory for manifests.
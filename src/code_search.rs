@@ -0,0 +1,434 @@
+//! Semantic codebase search.
+//!
+//! Registers a `search_code` external tool that retrieves code by meaning. The
+//! workspace is walked (honouring `.gitignore`), each source file is split into
+//! syntax-aware chunks via tree-sitter, and a local embedding is computed per
+//! chunk. Vectors are held by a [`MemoryStore`]; the default backend serializes
+//! them to the session-persist directory from `SessionPersistCli::path()`,
+//! while the Postgres backend offloads them to an external service (see
+//! [`crate::memory_store`]). The session-persist directory (or `.codex/` in the
+//! workspace) also caches the embedding model. Indexing is incremental: an `xxhash` of each
+//! file's bytes is stored so only changed files are re-embedded, and the index
+//! is refreshed before every query so in-session edits are tracked. A query is
+//! embedded and the top-k chunks by cosine similarity are returned, formatted
+//! like `read_file`'s `L{n}:` output so the model can re-open them precisely.
+
+use crate::memory_store::{MemoryStore, ScoredChunk, StoredChunk};
+use crate::syntax::{self, LanguageSupport};
+use async_trait::async_trait;
+use codex_core::{
+    FunctionCallError, ToolHandler, ToolInvocation, ToolKind, ToolOutput, ToolPayload,
+    config::Config, register_external_tool_handler,
+};
+use codex_protocol::ConversationId;
+use fastembed::{EmbeddingModel, InitOptions, TextEmbedding};
+use ignore::WalkBuilder;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokenizers::Tokenizer;
+use tree_sitter::{Node, Parser};
+
+/// Number of tokens a declaration chunk may span before it is split on child
+/// node boundaries. Measured with the model's tokenizer. Pieces produced by the
+/// named-children fallback in [`collect_chunks`] are not checked against it and
+/// may exceed it.
+const CHUNK_TOKEN_BUDGET: usize = 512;
+/// Hugging Face repo whose tokenizer matches the `AllMiniLML6V2` embedder.
+const TOKENIZER_REPO: &str = "sentence-transformers/all-MiniLM-L6-v2";
+/// Number of leading comment lines pulled in as context for each chunk.
+const LEADING_COMMENT_LINES: usize = 3;
+
+pub(crate) fn register_code_search_handler(
+    workspace: PathBuf,
+    persist_dir: Option<PathBuf>,
+    store: Box<dyn MemoryStore>,
+) {
+    let handler = CodeSearchHandler::new(workspace, persist_dir, store);
+    register_external_tool_handler("search_code", Arc::new(handler));
+}
+
+pub fn ensure_search_code_tool_enabled(config: &mut Config) {
+    if !config
+        .model_family
+        .experimental_supported_tools
+        .iter()
+        .any(|tool| tool == "search_code")
+    {
+        config
+            .model_family
+            .experimental_supported_tools
+            .push("search_code".to_string());
+    }
+}
+
+struct CodeSearchHandler {
+    workspace: PathBuf,
+    persist_dir: Option<PathBuf>,
+    store: Arc<dyn MemoryStore>,
+    index: Mutex<Option<Arc<CodeIndex>>>,
+}
+
+impl CodeSearchHandler {
+    fn new(workspace: PathBuf, persist_dir: Option<PathBuf>, store: Box<dyn MemoryStore>) -> Self {
+        Self {
+            workspace,
+            persist_dir,
+            store: Arc::from(store),
+            index: Mutex::new(None),
+        }
+    }
+
+    /// Load the (possibly persisted) index on first use; `search` refreshes it
+    /// against the workspace on every call so edits are tracked.
+    fn index(&self) -> Result<Arc<CodeIndex>, FunctionCallError> {
+        let mut guard = self.index.lock().expect("code index mutex poisoned");
+        if guard.is_none() {
+            let index = CodeIndex::load_or_build(
+                &self.workspace,
+                self.persist_dir.as_deref(),
+                self.store.clone(),
+            )
+            .map_err(|err| {
+                FunctionCallError::RespondToModel(format!("failed to build code index: {err}"))
+            })?;
+            *guard = Some(Arc::new(index));
+        }
+        Ok(guard.as_ref().expect("index initialized above").clone())
+    }
+}
+
+#[async_trait]
+impl ToolHandler for CodeSearchHandler {
+    fn kind(&self) -> ToolKind {
+        ToolKind::Function
+    }
+
+    async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
+        let arguments = match invocation.payload.clone() {
+            ToolPayload::Function { arguments } => arguments,
+            _ => {
+                return Err(FunctionCallError::RespondToModel(
+                    "search_code handler received unsupported payload".to_string(),
+                ));
+            }
+        };
+
+        let args: SearchCodeArgs = serde_json::from_str(&arguments).map_err(|err| {
+            FunctionCallError::RespondToModel(format!("failed to parse function arguments: {err:?}"))
+        })?;
+
+        if args.query.trim().is_empty() {
+            return Err(FunctionCallError::RespondToModel(
+                "query must not be empty".to_string(),
+            ));
+        }
+
+        let conversation = invocation.conversation_id();
+        let index = self.index()?;
+        let hits = index
+            .search(&conversation, &args.query, args.top_k)
+            .map_err(|err| {
+                FunctionCallError::RespondToModel(format!("search failed: {err}"))
+            })?;
+
+        Ok(ToolOutput::Function {
+            content: format_hits(&hits),
+            content_items: None,
+            success: Some(true),
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct SearchCodeArgs {
+    query: String,
+    #[serde(default = "defaults::top_k")]
+    top_k: usize,
+}
+
+mod defaults {
+    pub fn top_k() -> usize {
+        8
+    }
+}
+
+struct SearchHit {
+    file_path: PathBuf,
+    start_line: usize,
+    end_line: usize,
+    text: Option<String>,
+    score: f32,
+}
+
+/// Embeds workspace chunks into a [`MemoryStore`] and resolves queries through
+/// it. A lightweight text cache, keyed by `(path, start_line)`, keeps chunk
+/// bodies around for display regardless of which backend holds the vectors.
+struct CodeIndex {
+    workspace: PathBuf,
+    embedder: TextEmbedding,
+    tokenizer: Tokenizer,
+    store: Arc<dyn MemoryStore>,
+    text_cache: Mutex<HashMap<(PathBuf, usize), String>>,
+}
+
+impl CodeIndex {
+    fn load_or_build(
+        workspace: &Path,
+        persist_dir: Option<&Path>,
+        store: Arc<dyn MemoryStore>,
+    ) -> anyhow::Result<Self> {
+        let embedder = TextEmbedding::try_new(
+            InitOptions::new(EmbeddingModel::AllMiniLML6V2).with_cache_dir(
+                persist_dir
+                    .map(|dir| dir.join("embeddings"))
+                    .unwrap_or_else(|| workspace.join(".codex").join("embeddings")),
+            ),
+        )?;
+
+        let tokenizer = Tokenizer::from_pretrained(TOKENIZER_REPO, None)
+            .map_err(|err| anyhow::anyhow!("failed to load tokenizer: {err}"))?;
+
+        // The index is populated lazily by `search`, which refreshes on every
+        // call so edits made during a session are picked up.
+        Ok(Self {
+            workspace: workspace.to_path_buf(),
+            embedder,
+            tokenizer,
+            store,
+            text_cache: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Re-chunk and re-embed source files, upserting the resulting vectors into
+    /// the store. Hashing keeps the work incremental: a file whose `xxhash`
+    /// matches the one already stored is skipped (only its chunk text is cached
+    /// for display), and paths that disappeared from the workspace are deleted.
+    /// Run before every search so the index tracks edits made during a session.
+    fn refresh(&self, conversation: &ConversationId) -> anyhow::Result<()> {
+        let workspace = self.workspace.clone();
+        let workspace = workspace.as_path();
+        let prior = self.store.content_hashes(conversation)?;
+        let mut seen: HashSet<PathBuf> = HashSet::new();
+
+        for entry in WalkBuilder::new(workspace).hidden(false).build() {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(_) => continue,
+            };
+            if !entry.file_type().map(|ft| ft.is_file()).unwrap_or(false) {
+                continue;
+            }
+            let path = entry.path();
+            let Some(support) = syntax::support_for_path(path) else {
+                continue;
+            };
+            let Ok(content) = std::fs::read_to_string(path) else {
+                continue;
+            };
+            let hash = xxhash_rust::xxh3::xxh3_64(content.as_bytes());
+            seen.insert(path.to_path_buf());
+
+            let chunks = chunk_file(&content, &support, &self.tokenizer);
+            {
+                let mut cache = self.text_cache.lock().expect("text cache mutex poisoned");
+                for (start, _, text) in &chunks {
+                    cache.insert((path.to_path_buf(), *start), text.clone());
+                }
+            }
+
+            // Unchanged bytes: keep the stored vectors, skip re-embedding.
+            if prior.get(path) == Some(&hash) {
+                continue;
+            }
+
+            // Changed or new: replace the file's chunks wholesale so stale line
+            // spans do not linger after an edit.
+            self.store.delete_by_path(conversation, path)?;
+
+            let texts: Vec<String> = chunks.iter().map(|(_, _, text)| text.clone()).collect();
+            let mut pending: Vec<StoredChunk> = chunks
+                .into_iter()
+                .map(|(start, end, _)| StoredChunk {
+                    file_path: path.to_path_buf(),
+                    start_line: start,
+                    end_line: end,
+                    content_hash: hash,
+                    embedding: Vec::new(),
+                })
+                .collect();
+
+            if !texts.is_empty() {
+                let vectors = self.embedder.embed(texts, None)?;
+                for (chunk, vector) in pending.iter_mut().zip(vectors) {
+                    chunk.embedding = vector;
+                }
+                self.store.upsert(conversation, &pending)?;
+            }
+        }
+
+        // Drop index entries for files that no longer exist.
+        for path in prior.keys() {
+            if !seen.contains(path) {
+                self.store.delete_by_path(conversation, path)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn search(
+        &self,
+        conversation: &ConversationId,
+        query: &str,
+        top_k: usize,
+    ) -> anyhow::Result<Vec<SearchHit>> {
+        // Track any edits made since the last call before querying.
+        self.refresh(conversation)?;
+
+        let query_vec = self
+            .embedder
+            .embed(vec![query.to_string()], None)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("embedding model returned no vector"))?;
+
+        let scored = self.store.query(conversation, &query_vec, top_k)?;
+        let cache = self.text_cache.lock().expect("text cache mutex poisoned");
+        Ok(scored
+            .into_iter()
+            .map(|hit: ScoredChunk| SearchHit {
+                text: cache
+                    .get(&(hit.file_path.clone(), hit.start_line))
+                    .cloned(),
+                file_path: hit.file_path,
+                start_line: hit.start_line,
+                end_line: hit.end_line,
+                score: hit.score,
+            })
+            .collect())
+    }
+}
+
+/// Split a file into syntax-aware chunks: one per top-level or nested named
+/// declaration, with a few lines of leading-comment context. A declaration that
+/// fits [`CHUNK_TOKEN_BUDGET`] is emitted whole and its nested declarations are
+/// *not* emitted separately, so no text lands in two chunks; only an oversized
+/// declaration is split into its nested declarations (or, failing that, its
+/// named children).
+fn chunk_file(
+    content: &str,
+    support: &LanguageSupport,
+    tokenizer: &Tokenizer,
+) -> Vec<(usize, usize, String)> {
+    let mut parser = Parser::new();
+    if parser.set_language(&support.language).is_err() {
+        return Vec::new();
+    }
+    let Some(tree) = parser.parse(content, None) else {
+        return Vec::new();
+    };
+    let lines: Vec<&str> = content.lines().collect();
+
+    let mut chunks = Vec::new();
+    collect_chunks(tree.root_node(), support, &lines, tokenizer, &mut chunks);
+    chunks
+}
+
+fn collect_chunks(
+    node: Node,
+    support: &LanguageSupport,
+    lines: &[&str],
+    tokenizer: &Tokenizer,
+    chunks: &mut Vec<(usize, usize, String)>,
+) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        if !support.declarations.contains(&child.kind()) {
+            // Descend through non-declaration nodes to reach nested declarations.
+            collect_chunks(child, support, lines, tokenizer, chunks);
+            continue;
+        }
+
+        let start = child.start_position().row;
+        let end = child.end_position().row;
+        let ctx_start = leading_comment_start(lines, start);
+
+        if token_count(tokenizer, lines, ctx_start, end) <= CHUNK_TOKEN_BUDGET {
+            chunks.push((ctx_start + 1, end + 1, join_lines(lines, ctx_start, end)));
+            continue;
+        }
+
+        // Oversized: recurse into nested declarations instead of emitting the
+        // parent, so content is never duplicated.
+        let before = chunks.len();
+        collect_chunks(child, support, lines, tokenizer, chunks);
+        if chunks.len() == before {
+            // No nested declarations to split on; fall back to named children.
+            let mut inner = child.walk();
+            for grandchild in child.named_children(&mut inner) {
+                let cs = grandchild.start_position().row;
+                let ce = grandchild.end_position().row;
+                chunks.push((cs + 1, ce + 1, join_lines(lines, cs, ce)));
+            }
+        }
+    }
+}
+
+fn leading_comment_start(lines: &[&str], decl_start: usize) -> usize {
+    let mut start = decl_start;
+    let mut taken = 0;
+    while start > 0 && taken < LEADING_COMMENT_LINES {
+        let prev = lines[start - 1].trim_start();
+        if prev.starts_with("//") || prev.starts_with('#') || prev.starts_with("--") {
+            start -= 1;
+            taken += 1;
+        } else {
+            break;
+        }
+    }
+    start
+}
+
+fn join_lines(lines: &[&str], start: usize, end: usize) -> String {
+    lines[start..=end.min(lines.len().saturating_sub(1))].join("\n")
+}
+
+/// Token count for the `start..=end` line span, measured with the model's
+/// tokenizer. Falls back to a whitespace word count if encoding fails.
+fn token_count(tokenizer: &Tokenizer, lines: &[&str], start: usize, end: usize) -> usize {
+    let text = join_lines(lines, start, end);
+    match tokenizer.encode(text.as_str(), false) {
+        Ok(encoding) => encoding.len(),
+        Err(_) => text.split_whitespace().count(),
+    }
+}
+
+fn format_hits(hits: &[SearchHit]) -> String {
+    if hits.is_empty() {
+        return "no matching code found".to_string();
+    }
+    let mut out = String::new();
+    for hit in hits {
+        out.push_str(&format!(
+            "{} (L{}-L{}, score {:.3})\n",
+            hit.file_path.display(),
+            hit.start_line,
+            hit.end_line,
+            hit.score
+        ));
+        match &hit.text {
+            Some(text) => {
+                for (idx, line) in text.lines().enumerate() {
+                    out.push_str(&format!("L{}: {}\n", hit.start_line + idx, line));
+                }
+            }
+            None => {
+                out.push_str("  (open with read_file)\n");
+            }
+        }
+        out.push('\n');
+    }
+    out.trim_end().to_string()
+}
@@ -0,0 +1,335 @@
+//! Pluggable vector stores for the code-search index.
+//!
+//! The [`MemoryStore`] trait abstracts the vector index so large workspaces and
+//! long-lived sessions can offload embeddings instead of rebuilding them in
+//! memory each run. Two backends are provided: the default in-process
+//! [`LocalStore`], and [`PostgresStore`], which pushes embeddings to an external
+//! Postgres+pgvector service and issues similarity queries over SQL. Queries are
+//! scoped by [`ConversationId`] so multiple sessions can share one database
+//! without cross-contamination.
+
+use crate::cli::{MemoryBackend, MemoryCli};
+use codex_protocol::ConversationId;
+use pgvector::Vector;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// File name of the serialized local index inside the session-persist dir.
+const LOCAL_INDEX_FILE: &str = "code_index.json";
+
+/// Embedding dimension of the local model (`AllMiniLML6V2`). Declared on the
+/// pgvector column so ivfflat indexing succeeds.
+const EMBEDDING_DIM: usize = 384;
+
+/// A chunk vector as stored in a backend.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct StoredChunk {
+    pub file_path: PathBuf,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub content_hash: u64,
+    pub embedding: Vec<f32>,
+}
+
+/// A query result together with its cosine score.
+pub(crate) struct ScoredChunk {
+    pub file_path: PathBuf,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub score: f32,
+}
+
+/// Abstraction over a conversation-scoped vector index.
+pub(crate) trait MemoryStore: Send + Sync {
+    /// Insert or replace the vectors for a set of chunks.
+    fn upsert(&self, conversation: &ConversationId, chunks: &[StoredChunk]) -> anyhow::Result<()>;
+
+    /// Drop every chunk recorded for `path`.
+    fn delete_by_path(&self, conversation: &ConversationId, path: &std::path::Path)
+    -> anyhow::Result<()>;
+
+    /// Content hash previously stored for each indexed path, used to skip
+    /// re-embedding files whose bytes are unchanged.
+    fn content_hashes(
+        &self,
+        conversation: &ConversationId,
+    ) -> anyhow::Result<HashMap<PathBuf, u64>>;
+
+    /// Return the `top_k` chunks most similar to `query` by cosine similarity.
+    fn query(
+        &self,
+        conversation: &ConversationId,
+        query: &[f32],
+        top_k: usize,
+    ) -> anyhow::Result<Vec<ScoredChunk>>;
+}
+
+/// Construct the store selected on the command line. `persist_dir` is the
+/// session-persist directory from `SessionPersistCli::path()`, under which the
+/// local index is serialized so it survives across runs.
+pub(crate) fn store_from_cli(
+    cli: &MemoryCli,
+    persist_dir: Option<&Path>,
+) -> anyhow::Result<Box<dyn MemoryStore>> {
+    match cli.backend() {
+        MemoryBackend::Local => Ok(Box::new(LocalStore::load(persist_dir)?)),
+        MemoryBackend::Postgres => {
+            let url = cli.url().ok_or_else(|| {
+                anyhow::anyhow!("--memory-url is required when --memory-backend=postgres")
+            })?;
+            Ok(Box::new(PostgresStore::connect(url)?))
+        }
+    }
+}
+
+/// One conversation-scoped chunk as held by the local index.
+#[derive(Clone, Serialize, Deserialize)]
+struct LocalRecord {
+    conversation: String,
+    chunk: StoredChunk,
+}
+
+/// In-process vector index, serialized to the session-persist directory so the
+/// xxhash incremental path can skip unchanged files across runs.
+#[derive(Default)]
+pub(crate) struct LocalStore {
+    /// Destination for the serialized index, if session persistence is enabled.
+    path: Option<PathBuf>,
+    records: Mutex<Vec<LocalRecord>>,
+}
+
+impl LocalStore {
+    fn load(persist_dir: Option<&Path>) -> anyhow::Result<Self> {
+        let path = persist_dir.map(|dir| dir.join(LOCAL_INDEX_FILE));
+        let records = match path.as_ref() {
+            Some(path) if path.exists() => {
+                serde_json::from_slice(&std::fs::read(path)?).unwrap_or_default()
+            }
+            _ => Vec::new(),
+        };
+        Ok(Self {
+            path,
+            records: Mutex::new(records),
+        })
+    }
+
+    fn save(&self, records: &[LocalRecord]) -> anyhow::Result<()> {
+        let Some(path) = self.path.as_ref() else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_vec(records)?)?;
+        Ok(())
+    }
+}
+
+impl MemoryStore for LocalStore {
+    fn upsert(&self, conversation: &ConversationId, chunks: &[StoredChunk]) -> anyhow::Result<()> {
+        let conv = conversation.to_string();
+        let mut guard = self.records.lock().expect("local store mutex poisoned");
+        for chunk in chunks {
+            guard.retain(|record| {
+                record.conversation != conv
+                    || record.chunk.file_path != chunk.file_path
+                    || record.chunk.start_line != chunk.start_line
+            });
+            guard.push(LocalRecord {
+                conversation: conv.clone(),
+                chunk: chunk.clone(),
+            });
+        }
+        self.save(&guard)
+    }
+
+    fn delete_by_path(
+        &self,
+        conversation: &ConversationId,
+        path: &std::path::Path,
+    ) -> anyhow::Result<()> {
+        let conv = conversation.to_string();
+        let mut guard = self.records.lock().expect("local store mutex poisoned");
+        guard.retain(|record| record.conversation != conv || record.chunk.file_path != path);
+        self.save(&guard)
+    }
+
+    fn content_hashes(
+        &self,
+        conversation: &ConversationId,
+    ) -> anyhow::Result<HashMap<PathBuf, u64>> {
+        let conv = conversation.to_string();
+        let guard = self.records.lock().expect("local store mutex poisoned");
+        Ok(guard
+            .iter()
+            .filter(|record| record.conversation == conv)
+            .map(|record| (record.chunk.file_path.clone(), record.chunk.content_hash))
+            .collect())
+    }
+
+    fn query(
+        &self,
+        conversation: &ConversationId,
+        query: &[f32],
+        top_k: usize,
+    ) -> anyhow::Result<Vec<ScoredChunk>> {
+        let conv = conversation.to_string();
+        let guard = self.records.lock().expect("local store mutex poisoned");
+        let mut scored: Vec<ScoredChunk> = guard
+            .iter()
+            .filter(|record| record.conversation == conv)
+            .map(|record| ScoredChunk {
+                file_path: record.chunk.file_path.clone(),
+                start_line: record.chunk.start_line,
+                end_line: record.chunk.end_line,
+                score: cosine_similarity(query, &record.chunk.embedding),
+            })
+            .collect();
+        scored.sort_by(|a, b| b.score.total_cmp(&a.score));
+        scored.truncate(top_k.max(1));
+        Ok(scored)
+    }
+}
+
+/// Postgres+pgvector backend shared across sessions.
+pub(crate) struct PostgresStore {
+    client: Mutex<postgres::Client>,
+}
+
+impl PostgresStore {
+    fn connect(url: &str) -> anyhow::Result<Self> {
+        let mut client = postgres::Client::connect(url, postgres::NoTls)?;
+        client.batch_execute(&format!(
+            "CREATE EXTENSION IF NOT EXISTS vector;
+             CREATE TABLE IF NOT EXISTS code_chunks (
+                 conversation_id TEXT NOT NULL,
+                 file_path       TEXT NOT NULL,
+                 start_line      BIGINT NOT NULL,
+                 end_line        BIGINT NOT NULL,
+                 content_hash    BIGINT NOT NULL,
+                 embedding       vector({dim}) NOT NULL,
+                 PRIMARY KEY (conversation_id, file_path, start_line)
+             );
+             CREATE INDEX IF NOT EXISTS code_chunks_embedding_idx
+                 ON code_chunks USING ivfflat (embedding vector_cosine_ops)
+                 WITH (lists = 100);",
+            dim = EMBEDDING_DIM,
+        ))?;
+        Ok(Self {
+            client: Mutex::new(client),
+        })
+    }
+}
+
+impl MemoryStore for PostgresStore {
+    fn upsert(&self, conversation: &ConversationId, chunks: &[StoredChunk]) -> anyhow::Result<()> {
+        let mut client = self.client.lock().expect("postgres client mutex poisoned");
+        let conv = conversation.to_string();
+        for chunk in chunks {
+            client.execute(
+                "INSERT INTO code_chunks
+                     (conversation_id, file_path, start_line, end_line, content_hash, embedding)
+                 VALUES ($1, $2, $3, $4, $5, $6)
+                 ON CONFLICT (conversation_id, file_path, start_line) DO UPDATE
+                 SET end_line = EXCLUDED.end_line,
+                     content_hash = EXCLUDED.content_hash,
+                     embedding = EXCLUDED.embedding",
+                &[
+                    &conv,
+                    &chunk.file_path.to_string_lossy().as_ref(),
+                    &(chunk.start_line as i64),
+                    &(chunk.end_line as i64),
+                    &(chunk.content_hash as i64),
+                    &Vector::from(chunk.embedding.clone()),
+                ],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn delete_by_path(
+        &self,
+        conversation: &ConversationId,
+        path: &std::path::Path,
+    ) -> anyhow::Result<()> {
+        let mut client = self.client.lock().expect("postgres client mutex poisoned");
+        client.execute(
+            "DELETE FROM code_chunks WHERE conversation_id = $1 AND file_path = $2",
+            &[&conversation.to_string(), &path.to_string_lossy().as_ref()],
+        )?;
+        Ok(())
+    }
+
+    fn content_hashes(
+        &self,
+        conversation: &ConversationId,
+    ) -> anyhow::Result<HashMap<PathBuf, u64>> {
+        let mut client = self.client.lock().expect("postgres client mutex poisoned");
+        let rows = client.query(
+            "SELECT DISTINCT file_path, content_hash
+             FROM code_chunks
+             WHERE conversation_id = $1",
+            &[&conversation.to_string()],
+        )?;
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                (
+                    PathBuf::from(row.get::<_, String>("file_path")),
+                    row.get::<_, i64>("content_hash") as u64,
+                )
+            })
+            .collect())
+    }
+
+    fn query(
+        &self,
+        conversation: &ConversationId,
+        query: &[f32],
+        top_k: usize,
+    ) -> anyhow::Result<Vec<ScoredChunk>> {
+        let mut client = self.client.lock().expect("postgres client mutex poisoned");
+        let rows = client.query(
+            "SELECT file_path, start_line, end_line, 1 - (embedding <=> $2) AS score
+             FROM code_chunks
+             WHERE conversation_id = $1
+             ORDER BY embedding <=> $2
+             LIMIT $3",
+            &[
+                &conversation.to_string(),
+                &Vector::from(query.to_vec()),
+                &(top_k.max(1) as i64),
+            ],
+        )?;
+        Ok(rows
+            .into_iter()
+            .map(|row| ScoredChunk {
+                file_path: PathBuf::from(row.get::<_, String>("file_path")),
+                start_line: row.get::<_, i64>("start_line") as usize,
+                end_line: row.get::<_, i64>("end_line") as usize,
+                score: row.get::<_, f64>("score") as f32,
+            })
+            .collect())
+    }
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let mut dot = 0.0f32;
+    let mut na = 0.0f32;
+    let mut nb = 0.0f32;
+    for (x, y) in a.iter().zip(b.iter()) {
+        dot += x * y;
+        na += x * x;
+        nb += y * y;
+    }
+    if na == 0.0 || nb == 0.0 {
+        0.0
+    } else {
+        dot / (na.sqrt() * nb.sqrt())
+    }
+}
@@ -0,0 +1,146 @@
+//! File outline tool.
+//!
+//! `outline_file` returns a compact structural map of a file — one entry per
+//! top-level and nested declaration with its kind, name, and 1-indexed start
+//! line — so the model can see a whole file's shape in a few hundred tokens and
+//! jump straight to the right `offset` for a targeted `read_file`.
+
+use crate::syntax::{self, LanguageSupport};
+use async_trait::async_trait;
+use codex_core::{
+    FunctionCallError, ToolHandler, ToolInvocation, ToolKind, ToolOutput, ToolPayload,
+    config::Config, register_external_tool_handler,
+};
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tree_sitter::{Node, Parser};
+
+pub fn register_outline_file_handler() {
+    register_external_tool_handler("outline_file", Arc::new(OutlineFileHandler));
+}
+
+pub fn ensure_outline_file_tool_enabled(config: &mut Config) {
+    if !config
+        .model_family
+        .experimental_supported_tools
+        .iter()
+        .any(|tool| tool == "outline_file")
+    {
+        config
+            .model_family
+            .experimental_supported_tools
+            .push("outline_file".to_string());
+    }
+}
+
+struct OutlineFileHandler;
+
+#[async_trait]
+impl ToolHandler for OutlineFileHandler {
+    fn kind(&self) -> ToolKind {
+        ToolKind::Function
+    }
+
+    async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
+        let session_id = crate::read_file_tool::session_id_from_conversation_id(
+            &invocation.conversation_id(),
+        );
+
+        let arguments = match invocation.payload.clone() {
+            ToolPayload::Function { arguments } => arguments,
+            _ => {
+                return Err(FunctionCallError::RespondToModel(
+                    "outline_file handler received unsupported payload".to_string(),
+                ));
+            }
+        };
+
+        let args: OutlineFileArgs = serde_json::from_str(&arguments).map_err(|err| {
+            FunctionCallError::RespondToModel(format!("failed to parse function arguments: {err:?}"))
+        })?;
+
+        let path = PathBuf::from(&args.file_path);
+        if !path.is_absolute() {
+            return Err(FunctionCallError::RespondToModel(
+                "file_path must be an absolute path".to_string(),
+            ));
+        }
+
+        let Some(support) = syntax::support_for_path(&path) else {
+            return Err(FunctionCallError::RespondToModel(
+                "no bundled grammar for this file extension".to_string(),
+            ));
+        };
+
+        let content =
+            crate::read_file_tool::fetch_text(&session_id, path, None, None).await?;
+        if content.is_empty() {
+            return Err(FunctionCallError::RespondToModel(
+                "file is empty; nothing to outline".to_string(),
+            ));
+        }
+
+        let outline = build_outline(&content, &support).ok_or_else(|| {
+            FunctionCallError::RespondToModel("failed to parse file".to_string())
+        })?;
+
+        Ok(ToolOutput::Function {
+            content: outline,
+            content_items: None,
+            success: Some(true),
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct OutlineFileArgs {
+    file_path: String,
+}
+
+/// Pre-order walk collecting declaration nodes; indentation encodes nesting.
+fn build_outline(content: &str, support: &LanguageSupport) -> Option<String> {
+    let mut parser = Parser::new();
+    parser.set_language(&support.language).ok()?;
+    let tree = parser.parse(content, None)?;
+
+    let mut out = String::new();
+    walk(tree.root_node(), support, content, 0, &mut out);
+    if out.is_empty() {
+        out.push_str("(no declarations found)");
+    }
+    Some(out.trim_end().to_string())
+}
+
+fn walk(node: Node, support: &LanguageSupport, content: &str, depth: usize, out: &mut String) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        let next_depth = if support.declarations.contains(&child.kind()) {
+            let indent = "  ".repeat(depth);
+            let name = declaration_name(child, support, content).unwrap_or("<anon>");
+            out.push_str(&format!(
+                "{indent}{} {} (L{})\n",
+                child.kind(),
+                name,
+                child.start_position().row + 1
+            ));
+            depth + 1
+        } else {
+            depth
+        };
+        walk(child, support, content, next_depth, out);
+    }
+}
+
+fn declaration_name<'a>(
+    node: Node,
+    support: &LanguageSupport,
+    content: &'a str,
+) -> Option<&'a str> {
+    for field in support.name_fields {
+        if let Some(child) = node.child_by_field_name(field) {
+            return content.get(child.start_byte()..child.end_byte());
+        }
+    }
+    None
+}
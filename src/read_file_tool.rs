@@ -13,6 +13,7 @@ use std::future::Future;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::runtime::Handle;
+use tree_sitter::{Node, Parser, Point};
 
 pub fn register_remote_read_file_handler() {
     register_external_tool_handler("read_file", Arc::new(RemoteReadFileHandler::default()));
@@ -66,6 +67,7 @@ impl ToolHandler for RemoteReadFileHandler {
             limit,
             mode,
             indentation,
+            symbol,
         } = args;
 
         if offset == 0 {
@@ -93,6 +95,10 @@ impl ToolHandler for RemoteReadFileHandler {
                 let args = indentation.unwrap_or_default();
                 read_indent_block(&session_id, path, offset, limit, args).await?
             }
+            ReadMode::Symbol => {
+                let args = symbol.unwrap_or_default();
+                read_symbol_block(&session_id, path, offset, limit, args).await?
+            }
         };
 
         Ok(ToolOutput::Function {
@@ -114,6 +120,8 @@ struct ReadFileArgs {
     mode: ReadMode,
     #[serde(default)]
     indentation: Option<IndentationArgs>,
+    #[serde(default)]
+    symbol: Option<SymbolArgs>,
 }
 
 #[derive(Deserialize)]
@@ -121,6 +129,7 @@ struct ReadFileArgs {
 enum ReadMode {
     Slice,
     Indentation,
+    Symbol,
 }
 
 #[derive(Deserialize, Clone)]
@@ -149,6 +158,26 @@ impl Default for IndentationArgs {
     }
 }
 
+#[derive(Deserialize, Clone)]
+struct SymbolArgs {
+    #[serde(default)]
+    anchor_line: Option<usize>,
+    #[serde(default = "defaults::max_levels")]
+    max_levels: usize,
+    #[serde(default)]
+    max_lines: Option<usize>,
+}
+
+impl Default for SymbolArgs {
+    fn default() -> Self {
+        Self {
+            anchor_line: None,
+            max_levels: defaults::max_levels(),
+            max_lines: None,
+        }
+    }
+}
+
 impl Default for ReadMode {
     fn default() -> Self {
         ReadMode::Slice
@@ -242,6 +271,135 @@ async fn read_indent_block(
     read_block(records, offset, limit, options)
 }
 
+async fn read_symbol_block(
+    session_id: &SessionId,
+    path: PathBuf,
+    offset: usize,
+    limit: usize,
+    options: SymbolArgs,
+) -> Result<Vec<String>, FunctionCallError> {
+    let support = crate::syntax::support_for_path(&path);
+    let content = fetch_text(session_id, path.clone(), None, None).await?;
+    if content.is_empty() {
+        return Err(FunctionCallError::RespondToModel(
+            "file is empty; nothing to read".to_string(),
+        ));
+    }
+
+    // No bundled grammar: fall back to the indentation heuristic so the tool
+    // degrades gracefully instead of hard-failing on an unknown language.
+    let Some(support) = support else {
+        let fallback = IndentationArgs {
+            anchor_line: options.anchor_line,
+            max_levels: options.max_levels,
+            max_lines: options.max_lines,
+            ..IndentationArgs::default()
+        };
+        let records = collect_file_lines(&content);
+        return read_block(records, offset, limit, fallback);
+    };
+
+    match symbol_span(&content, &support, offset, &options) {
+        Some((start, end)) => Ok(emit_span(&content, start, end, limit, options.max_lines)),
+        None => {
+            // The file could not be parsed or no enclosing declaration was
+            // found; fall back to the indentation heuristic around the anchor.
+            let fallback = IndentationArgs {
+                anchor_line: options.anchor_line,
+                max_levels: options.max_levels,
+                max_lines: options.max_lines,
+                ..IndentationArgs::default()
+            };
+            let records = collect_file_lines(&content);
+            read_block(records, offset, limit, fallback)
+        }
+    }
+}
+
+/// Parse `content` and return the 0-indexed `[start, end]` row span of the
+/// smallest enclosing declaration node, climbing `max_levels` extra enclosing
+/// declarations.
+fn symbol_span(
+    content: &str,
+    support: &crate::syntax::LanguageSupport,
+    offset: usize,
+    options: &SymbolArgs,
+) -> Option<(usize, usize)> {
+    let mut parser = Parser::new();
+    if parser.set_language(&support.language).is_err() {
+        return None;
+    }
+    let tree = parser.parse(content, None)?;
+    let root = tree.root_node();
+
+    let anchor_line = options.anchor_line.unwrap_or(offset);
+    let anchor_row = anchor_line.saturating_sub(1);
+    let anchor_col = line_length(content, anchor_row);
+    let start_point = Point::new(anchor_row, 0);
+    let end_point = Point::new(anchor_row, anchor_col);
+
+    let anchor = root.named_descendant_for_point_range(start_point, end_point)?;
+
+    // Climb to the nearest declaration node, then `max_levels` more.
+    let mut declaration = declaration_ancestor(anchor, support);
+    let mut climbed = 0usize;
+    while climbed < options.max_levels {
+        let Some(current) = declaration else { break };
+        match current.parent().and_then(|p| declaration_ancestor(p, support)) {
+            Some(next) => {
+                declaration = Some(next);
+                climbed += 1;
+            }
+            None => break,
+        }
+    }
+
+    declaration.map(|decl| (decl.start_position().row, decl.end_position().row))
+}
+
+/// Return `node` if it is a declaration, otherwise its nearest declaration
+/// ancestor.
+fn declaration_ancestor<'a>(
+    node: Node<'a>,
+    support: &crate::syntax::LanguageSupport,
+) -> Option<Node<'a>> {
+    let mut current = Some(node);
+    while let Some(n) = current {
+        if support.declarations.contains(&n.kind()) {
+            return Some(n);
+        }
+        current = n.parent();
+    }
+    None
+}
+
+/// Byte column of the last character on `row`, used to build the anchor point
+/// range so tree-sitter resolves the node spanning the whole line.
+fn line_length(content: &str, row: usize) -> usize {
+    content.lines().nth(row).map(|line| line.len()).unwrap_or(0)
+}
+
+fn emit_span(
+    content: &str,
+    start_row: usize,
+    end_row: usize,
+    limit: usize,
+    max_lines: Option<usize>,
+) -> Vec<String> {
+    let cap = max_lines.map(|m| limit.min(m)).unwrap_or(limit);
+    content
+        .lines()
+        .enumerate()
+        .skip(start_row)
+        .take(end_row - start_row + 1)
+        .take(cap)
+        .map(|(idx, line)| {
+            let raw = line.trim_end_matches('\r');
+            format!("L{}: {}", idx + 1, format_line(raw.as_bytes()))
+        })
+        .collect()
+}
+
 fn collect_file_lines(content: &str) -> Vec<LineRecord> {
     content
         .lines()
@@ -432,7 +590,7 @@ fn split_lines(content: &str) -> Vec<String> {
         .collect()
 }
 
-async fn fetch_text(
+pub(crate) async fn fetch_text(
     session_id: &SessionId,
     path: PathBuf,
     line: Option<usize>,
@@ -451,7 +609,7 @@ async fn fetch_text(
         .map(|res| res.content)
 }
 
-fn session_id_from_conversation_id(id: &ConversationId) -> SessionId {
+pub(crate) fn session_id_from_conversation_id(id: &ConversationId) -> SessionId {
     SessionId(id.to_string().into())
 }
 
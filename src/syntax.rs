@@ -0,0 +1,82 @@
+//! Tree-sitter helpers shared by the read/outline/search tools.
+//!
+//! Grammars are selected by file extension; when no grammar is bundled for an
+//! extension the lookup returns `None`. `read_file`'s symbol mode then falls
+//! back to the indentation heuristic and `search_code` skips the file, while
+//! `outline_file` reports an error.
+
+use tree_sitter::Language;
+
+/// A bundled grammar together with the set of node kinds that count as a
+/// top-level or nested *declaration* for that language.
+pub(crate) struct LanguageSupport {
+    pub language: Language,
+    /// Node kinds treated as declaration boundaries (functions, classes, …).
+    pub declarations: &'static [&'static str],
+    /// Field names under which a declaration node carries its identifier,
+    /// tried in order.
+    pub name_fields: &'static [&'static str],
+}
+
+/// Resolve a [`LanguageSupport`] from a file extension, or `None` when no
+/// grammar is bundled for it.
+pub(crate) fn support_for_extension(ext: &str) -> Option<LanguageSupport> {
+    let lower = ext.to_ascii_lowercase();
+    match lower.as_str() {
+        "rs" => Some(LanguageSupport {
+            language: tree_sitter_rust::LANGUAGE.into(),
+            declarations: &[
+                "function_item",
+                "impl_item",
+                "struct_item",
+                "enum_item",
+                "trait_item",
+                "mod_item",
+                "macro_definition",
+            ],
+            name_fields: &["name", "type"],
+        }),
+        "py" | "pyi" => Some(LanguageSupport {
+            language: tree_sitter_python::LANGUAGE.into(),
+            declarations: &["function_definition", "class_definition"],
+            name_fields: &["name"],
+        }),
+        "js" | "jsx" | "mjs" | "cjs" => Some(LanguageSupport {
+            language: tree_sitter_javascript::LANGUAGE.into(),
+            declarations: &[
+                "function_declaration",
+                "class_declaration",
+                "method_definition",
+            ],
+            name_fields: &["name"],
+        }),
+        "ts" | "tsx" => Some(LanguageSupport {
+            language: tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            declarations: &[
+                "function_declaration",
+                "class_declaration",
+                "method_definition",
+                "interface_declaration",
+                "enum_declaration",
+            ],
+            name_fields: &["name"],
+        }),
+        "go" => Some(LanguageSupport {
+            language: tree_sitter_go::LANGUAGE.into(),
+            declarations: &[
+                "function_declaration",
+                "method_declaration",
+                "type_declaration",
+            ],
+            name_fields: &["name"],
+        }),
+        _ => None,
+    }
+}
+
+/// Resolve a [`LanguageSupport`] for a path's extension.
+pub(crate) fn support_for_path(path: &std::path::Path) -> Option<LanguageSupport> {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(support_for_extension)
+}